@@ -0,0 +1,139 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use message::Message;
+
+/// Outgoing actions a `Node` asks its caller to carry out, drained by `Node::poll_action`.
+#[derive(Debug, Clone)]
+pub enum Action<N, M> {
+    Send { destination: N, message: Message<N, M> },
+    Deliver { message_id: M },
+}
+
+/// The number of background (non-priority) actions kept when no explicit capacity is given.
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// A bounded queue of outgoing `Action`s, split into a priority class (GRAFT, PRUNE and deliver
+/// notifications, i.e., control traffic the tree's correctness depends on) and a background
+/// class (forwarded GOSSIP and IHAVE). `capacity` bounds only the background class: once full,
+/// the oldest background action is dropped to make room for the new one, and the drop is
+/// attributed to the dropped action's destination via `dropped_messages`. Priority actions are
+/// never dropped, and `pop` always drains them before any background action.
+#[derive(Debug)]
+pub struct ActionQueue<N, M> {
+    capacity: usize,
+    priority: VecDeque<Action<N, M>>,
+    background: VecDeque<Action<N, M>>,
+    dropped: HashMap<N, u64>,
+}
+impl<N, M> ActionQueue<N, M>
+where
+    N: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ActionQueue {
+            capacity,
+            priority: VecDeque::new(),
+            background: VecDeque::new(),
+            dropped: HashMap::new(),
+        }
+    }
+
+    pub fn send<T: Into<Message<N, M>>>(&mut self, destination: N, message: T) {
+        let message = message.into();
+        if is_priority(&message) {
+            self.priority.push_back(Action::Send { destination, message });
+        } else {
+            if self.background.len() >= self.capacity {
+                if let Some(Action::Send { destination: dropped, .. }) = self.background.pop_front() {
+                    *self.dropped.entry(dropped).or_insert(0) += 1;
+                }
+            }
+            self.background.push_back(Action::Send { destination, message });
+        }
+    }
+
+    pub fn deliver(&mut self, message_id: M) {
+        self.priority.push_back(Action::Deliver { message_id });
+    }
+
+    pub fn pop(&mut self) -> Option<Action<N, M>> {
+        self.priority.pop_front().or_else(|| self.background.pop_front())
+    }
+
+    /// Number of background actions dropped for `peer` so far due to capacity pressure.
+    pub fn dropped_messages(&self, peer: &N) -> u64 {
+        self.dropped.get(peer).cloned().unwrap_or(0)
+    }
+}
+impl<N, M> Default for ActionQueue<N, M>
+where
+    N: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_priority<N, M>(message: &Message<N, M>) -> bool {
+    match *message {
+        Message::Graft(_) | Message::Prune(_) => true,
+        Message::Gossip(_) | Message::Ihave(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use message::{GossipMessage, PruneMessage};
+
+    fn gossip(sender: u32, message_id: u32) -> GossipMessage<u32, u32> {
+        GossipMessage { sender, message_id, round: 0 }
+    }
+
+    #[test]
+    fn drops_oldest_background_action_once_over_capacity() {
+        let mut queue: ActionQueue<u32, u32> = ActionQueue::with_capacity(2);
+        queue.send(1, gossip(0, 100));
+        queue.send(2, gossip(0, 200));
+        queue.send(3, gossip(0, 300)); // over capacity: drops peer 1's action
+
+        assert_eq!(queue.dropped_messages(&1), 1);
+        assert_eq!(queue.dropped_messages(&2), 0);
+
+        match queue.pop().unwrap() {
+            Action::Send { destination, .. } => assert_eq!(destination, 2),
+            other => panic!("expected a Send action, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn priority_actions_are_never_dropped_and_drain_first() {
+        let mut queue: ActionQueue<u32, u32> = ActionQueue::with_capacity(1);
+        queue.send(1, gossip(0, 100));
+        queue.send(2, gossip(0, 200)); // drops peer 1's background action
+        queue.deliver(100);
+        queue.send(3, PruneMessage::new(&0));
+
+        assert_eq!(queue.dropped_messages(&1), 1);
+        assert!(matches!(queue.pop(), Some(Action::Deliver { .. })));
+        match queue.pop().unwrap() {
+            Action::Send { destination, .. } => assert_eq!(destination, 3),
+            other => panic!("expected peer 3's PRUNE, got {:?}", other),
+        }
+        match queue.pop().unwrap() {
+            Action::Send { destination, .. } => assert_eq!(destination, 2),
+            other => panic!("expected peer 2's background GOSSIP, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_matches_new() {
+        let queue: ActionQueue<u32, u32> = ActionQueue::default();
+        assert_eq!(queue.dropped_messages(&1), 0);
+    }
+}
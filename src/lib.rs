@@ -0,0 +1,9 @@
+//! An implementation of the Plumtree (Epidemic Broadcast Trees) gossip protocol.
+
+pub mod action;
+pub mod message;
+mod node;
+
+pub use action::Action;
+pub use message::Message;
+pub use node::Node;
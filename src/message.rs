@@ -0,0 +1,76 @@
+/// A gossip message carrying an application payload identifier, forwarded eagerly along the
+/// spanning tree.
+#[derive(Debug, Clone)]
+pub struct GossipMessage<N, M> {
+    pub sender: N,
+    pub message_id: M,
+    pub round: u64,
+}
+
+/// A lazy announcement that `sender` has `message_id`, without the payload itself.
+#[derive(Debug, Clone)]
+pub struct IhaveMessage<N, M> {
+    pub sender: N,
+    pub message_id: M,
+    pub round: u64,
+}
+
+/// Requests that `sender` be sent `message_id` (or, if `None`, requests that the recipient
+/// start eager-pushing to `sender` again without resending any particular message).
+#[derive(Debug, Clone)]
+pub struct GraftMessage<N, M> {
+    pub sender: N,
+    pub message_id: Option<M>,
+    pub round: u64,
+}
+
+/// Asks the recipient to move `sender` from its eager push peers to its lazy push peers.
+#[derive(Debug, Clone)]
+pub struct PruneMessage<N> {
+    pub sender: N,
+}
+impl<N: Clone> PruneMessage<N> {
+    pub fn new(sender: &N) -> Self {
+        PruneMessage {
+            sender: sender.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message<N, M> {
+    Gossip(GossipMessage<N, M>),
+    Ihave(IhaveMessage<N, M>),
+    Graft(GraftMessage<N, M>),
+    Prune(PruneMessage<N>),
+}
+impl<N, M> Message<N, M> {
+    pub fn sender(&self) -> &N {
+        match *self {
+            Message::Gossip(ref m) => &m.sender,
+            Message::Ihave(ref m) => &m.sender,
+            Message::Graft(ref m) => &m.sender,
+            Message::Prune(ref m) => &m.sender,
+        }
+    }
+}
+impl<N, M> From<GossipMessage<N, M>> for Message<N, M> {
+    fn from(f: GossipMessage<N, M>) -> Self {
+        Message::Gossip(f)
+    }
+}
+impl<N, M> From<IhaveMessage<N, M>> for Message<N, M> {
+    fn from(f: IhaveMessage<N, M>) -> Self {
+        Message::Ihave(f)
+    }
+}
+impl<N, M> From<GraftMessage<N, M>> for Message<N, M> {
+    fn from(f: GraftMessage<N, M>) -> Self {
+        Message::Graft(f)
+    }
+}
+impl<N, M> From<PruneMessage<N>> for Message<N, M> {
+    fn from(f: PruneMessage<N>) -> Self {
+        Message::Prune(f)
+    }
+}
@@ -1,9 +1,75 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 
 use action::{Action, ActionQueue};
 use message::{GossipMessage, GraftMessage, IhaveMessage, Message, PruneMessage};
 
+/// Delay (in ticks) before the first GRAFT is scheduled for an unreceived message.
+const DEFAULT_IHAVE_TIMEOUT1: u64 = 5;
+
+/// Delay (in ticks) before a follow-up GRAFT is scheduled if the first one goes unanswered.
+const DEFAULT_IHAVE_TIMEOUT2: u64 = 1;
+
+/// Number of ticks a received message id is remembered for before it's forgotten automatically.
+const DEFAULT_MESSAGE_HISTORY_LENGTH: u64 = 100;
+
+/// Number of background (non-priority) actions the outgoing `ActionQueue` keeps before it starts
+/// dropping the oldest one to make room.
+const DEFAULT_ACTION_QUEUE_CAPACITY: usize = 4096;
+
+/// Weight assumed for a peer that was added without an explicit weight.
+const DEFAULT_PEER_WEIGHT: u64 = 1;
+
+/// Factor by which a duplicate's sender weight must exceed the first-delivery path's weight
+/// (strictly more than `margin * first_weight`) before the duplicate is kept eager instead of
+/// PRUNEd.
+const DEFAULT_PRUNE_WEIGHT_MARGIN: u64 = 2;
+
+/// Score delta for a peer that delivers a message before anyone else.
+const SCORE_FIRST_DELIVERY: f64 = 1.0;
+
+/// Score delta for a peer whose duplicate GOSSIP forces a PRUNE.
+const SCORE_DUPLICATE: f64 = -0.5;
+
+/// Score delta for each GRAFT/PRUNE received from a peer, penalizing eager/lazy churn.
+const SCORE_CHURN: f64 = -0.1;
+
+/// Score delta for a peer whose IHAVE announcement expires without ever being followed by the
+/// real GOSSIP.
+const SCORE_STALE_IHAVE: f64 = -1.0;
+
+/// Multiplier applied to every peer's score on each tick, so old behavior is gradually forgotten.
+const DEFAULT_SCORE_DECAY: f64 = 0.98;
+
+/// Score below which a peer is demoted out of the eager push set and sent a PRUNE.
+const DEFAULT_SCORE_THRESHOLD: f64 = -5.0;
+
+/// Tunables for a `Node`, with defaults matching the values used by `Node::new`.
+#[derive(Debug, Clone)]
+pub struct NodeOptions {
+    pub ihave_timeout1: u64,
+    pub ihave_timeout2: u64,
+    pub message_history_length: u64,
+    pub prune_weight_margin: u64,
+    pub score_decay: f64,
+    pub score_threshold: f64,
+    pub action_queue_capacity: usize,
+}
+impl Default for NodeOptions {
+    fn default() -> Self {
+        NodeOptions {
+            ihave_timeout1: DEFAULT_IHAVE_TIMEOUT1,
+            ihave_timeout2: DEFAULT_IHAVE_TIMEOUT2,
+            message_history_length: DEFAULT_MESSAGE_HISTORY_LENGTH,
+            prune_weight_margin: DEFAULT_PRUNE_WEIGHT_MARGIN,
+            score_decay: DEFAULT_SCORE_DECAY,
+            score_threshold: DEFAULT_SCORE_THRESHOLD,
+            action_queue_capacity: DEFAULT_ACTION_QUEUE_CAPACITY,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Node<N, M>
 where
@@ -13,24 +79,39 @@ where
     node_id: N,
     eager_push_peers: HashSet<N>, // TODO: Vec?
     lazy_push_peers: HashSet<N>,
+    peer_weights: HashMap<N, u64>,
+    prune_weight_margin: u64,
+    peer_scores: HashMap<N, f64>,
+    score_decay: f64,
+    score_threshold: f64,
     missing: MissingMessages<N, M>,
-    received_msgs: HashSet<M>,
+    received_msgs: MessageCache<N, M>,
     action_queue: ActionQueue<N, M>,
     clock: u64,
 }
 impl<N, M> Node<N, M>
 where
     N: Hash + Eq + Clone,
-    M: Hash + Eq + Clone,
+    M: Hash + Eq + Ord + Clone,
 {
     pub fn new(node_id: N) -> Self {
+        Self::with_options(node_id, NodeOptions::default())
+    }
+
+    /// Like [`new`](Node::new), but with explicit tunables instead of the defaults.
+    pub fn with_options(node_id: N, options: NodeOptions) -> Self {
         Node {
             node_id,
             eager_push_peers: HashSet::new(),
             lazy_push_peers: HashSet::new(),
-            missing: MissingMessages::new(),
-            received_msgs: HashSet::new(),
-            action_queue: ActionQueue::new(),
+            peer_weights: HashMap::new(),
+            prune_weight_margin: options.prune_weight_margin,
+            peer_scores: HashMap::new(),
+            score_decay: options.score_decay,
+            score_threshold: options.score_threshold,
+            missing: MissingMessages::new(options.ihave_timeout1, options.ihave_timeout2),
+            received_msgs: MessageCache::new(options.message_history_length),
+            action_queue: ActionQueue::with_capacity(options.action_queue_capacity),
             clock: 0,
         }
     }
@@ -48,31 +129,68 @@ where
     }
 
     pub fn handle_neighbour_up(&mut self, neighbour_node_id: N) {
+        self.handle_neighbour_up_with_weight(neighbour_node_id, DEFAULT_PEER_WEIGHT);
+    }
+
+    /// Like [`handle_neighbour_up`](Node::handle_neighbour_up), but records `weight` (e.g. the
+    /// peer's stake) alongside it. Higher-weighted peers are favoured when forwarding via
+    /// `eager_push` and are more resistant to being PRUNEd on a duplicate GOSSIP.
+    pub fn handle_neighbour_up_with_weight(&mut self, neighbour_node_id: N, weight: u64) {
         if self.node_id == neighbour_node_id {
             // TODO: metrics
             return;
         }
+        self.peer_weights.insert(neighbour_node_id.clone(), weight);
         self.eager_push_peers.insert(neighbour_node_id);
     }
 
     pub fn handle_neighbour_down(&mut self, neighbour_node_id: N) {
         self.eager_push_peers.remove(&neighbour_node_id);
         self.lazy_push_peers.remove(&neighbour_node_id);
+        self.peer_weights.remove(&neighbour_node_id);
+        self.peer_scores.remove(&neighbour_node_id);
         self.missing.handle_node_down(&neighbour_node_id);
     }
 
+    /// Returns `node_id`'s current behavior score, or `0.0` if nothing has been observed yet.
+    /// Rises as a peer delivers messages first and falls as it spams duplicates, churns its
+    /// GRAFT/PRUNE state, or announces IHAVEs that never materialize into GOSSIP.
+    pub fn peer_score(&self, node_id: &N) -> f64 {
+        self.peer_scores.get(node_id).cloned().unwrap_or(0.0)
+    }
+
+    /// Number of background (non-priority) actions dropped for `peer` so far because the
+    /// outgoing action queue was at capacity.
+    pub fn dropped_messages(&self, peer: &N) -> u64 {
+        self.action_queue.dropped_messages(peer)
+    }
+
+    /// Explicitly forgets a received message ahead of the cache's own history window, e.g.
+    /// because the caller knows it will never be GRAFTed again.
     pub fn forget_message(&mut self, message_id: &M) {
-        self.received_msgs.remove(message_id);
+        self.received_msgs.forget(message_id);
     }
 
     pub fn handle_tick(&mut self) {
         self.clock += 1;
-        while let Some(ihave) = self.missing.pop_expired(self.clock) {
+        self.received_msgs.evict_older_than(self.clock);
+        for score in self.peer_scores.values_mut() {
+            *score *= self.score_decay;
+        }
+        while let Some((ihave, failed_sender)) = self.missing.pop_expired(self.clock) {
+            if let Some(failed_sender) = failed_sender {
+                // This message id's timer has fired before: the peer we GRAFTed onto last time
+                // never followed through with the real GOSSIP. A first expiry is just the
+                // normal recovery path and isn't penalized.
+                self.adjust_score(&failed_sender, SCORE_STALE_IHAVE);
+            }
             if !self.is_known_node(&ihave.sender) {
                 continue;
             }
-            self.eager_push_peers.insert(ihave.sender.clone());
-            self.lazy_push_peers.remove(&ihave.sender);
+            if self.peer_score(&ihave.sender) >= self.score_threshold {
+                self.eager_push_peers.insert(ihave.sender.clone());
+                self.lazy_push_peers.remove(&ihave.sender);
+            }
             self.action_queue.send(
                 ihave.sender,
                 GraftMessage {
@@ -90,20 +208,42 @@ where
 
     fn handle_gossip(&mut self, m: GossipMessage<N, M>) {
         if self.received_msgs.contains(&m.message_id) {
-            self.eager_push_peers.remove(&m.sender);
-            self.lazy_push_peers.insert(m.sender.clone());
-            self.action_queue
-                .send(m.sender, PruneMessage::new(&self.node_id));
+            let first_weight = self
+                .received_msgs
+                .first_sender(&m.message_id)
+                .map(|s| self.peer_weight(s))
+                .unwrap_or(DEFAULT_PEER_WEIGHT);
+            let sender_weight = self.peer_weight(&m.sender);
+            // A duplicate costs score regardless of stake, so a high-weight peer that spams
+            // duplicates still gets demoted once its score crosses the threshold; stake only
+            // buys tolerance for the occasional redundant delivery, not a free pass.
+            self.adjust_score(&m.sender, SCORE_DUPLICATE);
+            if sender_weight > first_weight.saturating_mul(self.prune_weight_margin)
+                && self.peer_score(&m.sender) >= self.score_threshold
+            {
+                // The duplicate arrived over a substantially higher-stake path than the one that
+                // delivered first; keep it eager rather than PRUNE it away.
+                self.eager_push_peers.insert(m.sender.clone());
+                self.lazy_push_peers.remove(&m.sender);
+            } else if self.eager_push_peers.remove(&m.sender) {
+                self.lazy_push_peers.insert(m.sender.clone());
+                self.action_queue
+                    .send(m.sender, PruneMessage::new(&self.node_id));
+            }
         } else {
+            self.adjust_score(&m.sender, SCORE_FIRST_DELIVERY);
             self.action_queue.deliver(m.message_id.clone());
-            self.received_msgs.insert(m.message_id.clone());
-            self.missing.cancel_timer(&m.message_id);
+            self.received_msgs
+                .insert(self.clock, m.message_id.clone(), m.sender.clone());
 
             self.eager_push(m.clone());
             self.lazy_push(m.clone());
             self.eager_push_peers.insert(m.sender.clone());
             self.lazy_push_peers.remove(&m.sender);
-            self.optimize(m);
+            // `optimize` needs to see the still-pending MissingMessages entry for this id, so
+            // it must run before the timer for it is cancelled below.
+            self.optimize(m.clone());
+            self.missing.cancel_timer(&m.message_id);
         }
     }
 
@@ -111,12 +251,15 @@ where
         if self.received_msgs.contains(&m.message_id) {
             return;
         }
-        self.missing.push(m); // TODO: increase timeout if already exists
+        self.missing.push(self.clock, m);
     }
 
     fn handle_graft(&mut self, mut m: GraftMessage<N, M>) {
-        self.eager_push_peers.insert(m.sender.clone());
-        self.lazy_push_peers.remove(&m.sender);
+        self.adjust_score(&m.sender, SCORE_CHURN);
+        if self.peer_score(&m.sender) >= self.score_threshold {
+            self.eager_push_peers.insert(m.sender.clone());
+            self.lazy_push_peers.remove(&m.sender);
+        }
         if let Some(message_id) = m.message_id.take() {
             if self.received_msgs.contains(&message_id) {
                 self.action_queue.send(
@@ -132,6 +275,7 @@ where
     }
 
     fn handle_prune(&mut self, m: PruneMessage<N>) {
+        self.adjust_score(&m.sender, SCORE_CHURN);
         self.eager_push_peers.remove(&m.sender);
         self.lazy_push_peers.insert(m.sender);
     }
@@ -140,7 +284,12 @@ where
         let sender = m.sender;
         m.sender = self.node_id.clone();
         m.round = m.round.saturating_add(1);
-        for p in self.eager_push_peers.iter().filter(|n| **n != sender) {
+        let mut peers: Vec<&N> = self.eager_push_peers.iter().filter(|n| **n != sender).collect();
+        // Lowest weight first: `ActionQueue` sheds load by dropping the *oldest* background
+        // action, so enqueueing low-stake peers first makes them the ones aged out under
+        // pressure, while high-stake peers are enqueued last and so are retained longest.
+        peers.sort_by_key(|n| self.peer_weight(n));
+        for p in peers {
             self.action_queue.send(p.clone(), m.clone());
         }
     }
@@ -178,35 +327,399 @@ where
     fn is_known_node(&self, node_id: &N) -> bool {
         self.eager_push_peers.contains(node_id) || self.lazy_push_peers.contains(node_id)
     }
+
+    fn peer_weight(&self, node_id: &N) -> u64 {
+        self.peer_weights.get(node_id).cloned().unwrap_or(DEFAULT_PEER_WEIGHT)
+    }
+
+    /// Applies `delta` to `node_id`'s score and, if it has fallen below `score_threshold`,
+    /// demotes the peer out of the eager push set and PRUNEs it.
+    fn adjust_score(&mut self, node_id: &N, delta: f64) {
+        let score = {
+            let score = self.peer_scores.entry(node_id.clone()).or_insert(0.0);
+            *score += delta;
+            *score
+        };
+        if score < self.score_threshold && self.eager_push_peers.remove(node_id) {
+            self.lazy_push_peers.insert(node_id.clone());
+            self.action_queue
+                .send(node_id.clone(), PruneMessage::new(&self.node_id));
+        }
+    }
 }
 
+/// Tracks IHAVE announcements for messages that have not yet arrived via GOSSIP, and schedules
+/// when to GRAFT onto the peer most likely to have them.
+///
+/// Every known message id maps to the announcements collected for it so far, kept sorted by
+/// `round` so the lowest-round (i.e., closest to the original source) candidate is always
+/// `entries[0]`. A separate min-heap schedules when each message id becomes eligible for a
+/// GRAFT: the first announcement arms the timer at `now + timeout1`; if `pop_expired` fires and
+/// other candidates remain for that message, it's rearmed at the shorter `now + timeout2` so an
+/// unresponsive graft target doesn't stall recovery for long. `last_attempt` remembers which
+/// sender the previous GRAFT for a message id went to, so callers can tell a first (expected)
+/// timeout1 expiry apart from a later expiry that means that sender never delivered.
 #[derive(Debug)]
-struct MissingMessages<N, M>(::std::marker::PhantomData<(N, M)>);
-impl<N, M> MissingMessages<N, M> {
-    fn new() -> Self {
-        MissingMessages(::std::marker::PhantomData)
+struct MissingMessages<N, M> {
+    timeout1: u64,
+    timeout2: u64,
+    entries: HashMap<M, Vec<IhaveMessage<N, M>>>,
+    schedule: BinaryHeap<Reverse<(u64, M)>>,
+    last_attempt: HashMap<M, N>,
+}
+impl<N, M> MissingMessages<N, M>
+where
+    M: Hash + Eq + Ord + Clone,
+{
+    fn new(timeout1: u64, timeout2: u64) -> Self {
+        MissingMessages {
+            timeout1,
+            timeout2,
+            entries: HashMap::new(),
+            schedule: BinaryHeap::new(),
+            last_attempt: HashMap::new(),
+        }
     }
 
-    fn push(&mut self, m: IhaveMessage<N, M>) {}
+    fn push(&mut self, now: u64, m: IhaveMessage<N, M>)
+    where
+        N: PartialEq,
+    {
+        let entries = self.entries.entry(m.message_id.clone()).or_default();
+        if entries.is_empty() {
+            self.schedule.push(Reverse((now + self.timeout1, m.message_id.clone())));
+        }
+        // A peer may retransmit the same announcement; keep at most one entry per sender rather
+        // than letting duplicates pile up and drag out GRAFT retries.
+        if let Some(pos) = entries.iter().position(|e| e.sender == m.sender) {
+            entries.remove(pos);
+        }
+        let i = entries.binary_search_by_key(&m.round, |e| e.round).unwrap_or_else(|i| i);
+        entries.insert(i, m);
+    }
 
-    fn pop_expired(&mut self, now: u64) -> Option<IhaveMessage<N, M>> {
-        panic!()
+    /// Pops the next message id whose GRAFT timer has expired, returning the announcement to
+    /// GRAFT onto next, plus the sender of the *previous* GRAFT attempt for that same message
+    /// id, if any — present only when this isn't the first expiry, i.e. when that previous
+    /// sender had a chance to deliver the GOSSIP and didn't.
+    fn pop_expired(&mut self, now: u64) -> Option<(IhaveMessage<N, M>, Option<N>)>
+    where
+        N: Clone,
+    {
+        while let Some(&Reverse((deadline, _))) = self.schedule.peek() {
+            if deadline > now {
+                break;
+            }
+            let Reverse((_, message_id)) = self.schedule.pop().expect("peeked above");
+            let entries = match self.entries.get_mut(&message_id) {
+                Some(entries) if !entries.is_empty() => entries,
+                _ => {
+                    self.last_attempt.remove(&message_id);
+                    continue;
+                }
+            };
+            let best = entries.remove(0);
+            let failed_sender = self.last_attempt.insert(message_id.clone(), best.sender.clone());
+            if entries.is_empty() {
+                // No candidate left to retry with, so there's nothing left to remember either.
+                self.entries.remove(&message_id);
+                self.last_attempt.remove(&message_id);
+            } else {
+                self.schedule.push(Reverse((now + self.timeout2, message_id)));
+            }
+            return Some((best, failed_sender));
+        }
+        None
     }
 
-    fn cancel_timer(&mut self, _message_id: &M) {}
+    fn cancel_timer(&mut self, message_id: &M) {
+        self.entries.remove(message_id);
+        self.last_attempt.remove(message_id);
+    }
 
-    fn handle_node_down(&mut self, _node_id: &N) {}
+    fn handle_node_down(&mut self, node_id: &N)
+    where
+        N: PartialEq,
+    {
+        self.entries.retain(|_, entries| {
+            entries.retain(|ihave| &ihave.sender != node_id);
+            !entries.is_empty()
+        });
+    }
 
-    fn is_empty(&self) -> bool {
-        panic!()
+    /// Returns the pending announcement with the minimum round for `message_id`, if any.
+    fn get_by_id(&self, message_id: &M) -> Option<&IhaveMessage<N, M>> {
+        self.entries.get(message_id).and_then(|entries| entries.first())
     }
+}
 
-    fn contains(&self, _message_id: &M) -> bool {
-        panic!()
+/// A time-windowed cache of received message ids, modeled on gossipsub's `mcache`: ids are
+/// recorded in the bucket for the round they arrived in, and buckets older than
+/// `history_length` rounds are evicted wholesale on each tick, bounding memory without the
+/// caller having to track what's safe to forget. `forget` remains available as an explicit
+/// override for ids the caller knows are safe to drop early. Each id also remembers the peer
+/// that delivered it first, so later duplicates can be weighed against that path.
+#[derive(Debug)]
+struct MessageCache<N, M> {
+    history_length: u64,
+    senders: HashMap<M, N>,
+    buckets: VecDeque<(u64, Vec<M>)>,
+}
+impl<N, M> MessageCache<N, M>
+where
+    M: Hash + Eq + Clone,
+{
+    fn new(history_length: u64) -> Self {
+        MessageCache {
+            history_length,
+            senders: HashMap::new(),
+            buckets: VecDeque::new(),
+        }
     }
 
-    fn get_by_id(&self, _message_id: &M) -> Option<&IhaveMessage<N, M>> {
-        // NOTE: returns minimum round node
-        panic!()
+    fn insert(&mut self, round: u64, message_id: M, sender: N) {
+        self.senders.insert(message_id.clone(), sender);
+        match self.buckets.back_mut() {
+            Some(&mut (bucket_round, ref mut ids)) if bucket_round == round => {
+                ids.push(message_id);
+            }
+            _ => {
+                self.buckets.push_back((round, vec![message_id]));
+            }
+        }
+    }
+
+    fn contains(&self, message_id: &M) -> bool {
+        self.senders.contains_key(message_id)
+    }
+
+    fn first_sender(&self, message_id: &M) -> Option<&N> {
+        self.senders.get(message_id)
+    }
+
+    fn forget(&mut self, message_id: &M) {
+        self.senders.remove(message_id);
+    }
+
+    fn evict_older_than(&mut self, now: u64) {
+        while let Some(&(round, _)) = self.buckets.front() {
+            if now.saturating_sub(round) < self.history_length {
+                break;
+            }
+            let (_, ids) = self.buckets.pop_front().expect("peeked above");
+            for id in ids {
+                self.senders.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihave(sender: u32, message_id: u32, round: u64) -> IhaveMessage<u32, u32> {
+        IhaveMessage { sender, message_id, round }
+    }
+
+    #[test]
+    fn pop_expired_waits_until_timeout1() {
+        let mut missing: MissingMessages<u32, u32> = MissingMessages::new(5, 1);
+        missing.push(0, ihave(1, 100, 0));
+        assert!(missing.pop_expired(4).is_none());
+        assert!(missing.pop_expired(5).is_some());
+    }
+
+    #[test]
+    fn pop_expired_reschedules_at_timeout2_and_reports_the_failed_sender() {
+        let mut missing: MissingMessages<u32, u32> = MissingMessages::new(5, 1);
+        missing.push(0, ihave(1, 100, 2));
+        missing.push(0, ihave(2, 100, 0));
+
+        // First expiry: the lowest-round candidate (sender 2) is tried, and since this is the
+        // first attempt for this message id there is no previous failure to report.
+        let (best, failed) = missing.pop_expired(5).unwrap();
+        assert_eq!(best.sender, 2);
+        assert_eq!(failed, None);
+
+        // Not yet due for the shorter timeout2 retry.
+        assert!(missing.pop_expired(5).is_none());
+
+        // The retry fires after timeout2 and now reports sender 2 as having failed to deliver.
+        let (best, failed) = missing.pop_expired(6).unwrap();
+        assert_eq!(best.sender, 1);
+        assert_eq!(failed, Some(2));
+    }
+
+    #[test]
+    fn cancel_timer_drops_all_candidates_for_a_message() {
+        let mut missing: MissingMessages<u32, u32> = MissingMessages::new(5, 1);
+        missing.push(0, ihave(1, 100, 0));
+        missing.cancel_timer(&100);
+        assert!(missing.pop_expired(5).is_none());
+    }
+
+    #[test]
+    fn handle_node_down_purges_only_that_senders_announcements() {
+        let mut missing: MissingMessages<u32, u32> = MissingMessages::new(5, 1);
+        missing.push(0, ihave(1, 100, 1));
+        missing.push(0, ihave(2, 100, 0));
+        missing.handle_node_down(&2);
+        let (best, _) = missing.pop_expired(5).unwrap();
+        assert_eq!(best.sender, 1);
+    }
+
+    #[test]
+    fn message_cache_evicts_buckets_older_than_history_length() {
+        let mut cache: MessageCache<u32, u32> = MessageCache::new(3);
+        cache.insert(0, 100, 1);
+        cache.insert(1, 200, 2);
+        assert!(cache.contains(&100));
+
+        cache.evict_older_than(2);
+        assert!(cache.contains(&100));
+        assert!(cache.contains(&200));
+
+        cache.evict_older_than(3);
+        assert!(!cache.contains(&100));
+        assert!(cache.contains(&200));
+    }
+
+    #[test]
+    fn message_cache_forget_is_an_explicit_early_override() {
+        let mut cache: MessageCache<u32, u32> = MessageCache::new(100);
+        cache.insert(0, 100, 1);
+        cache.forget(&100);
+        assert!(!cache.contains(&100));
+    }
+
+    #[test]
+    fn eager_push_enqueues_low_weight_peers_first_so_they_drop_before_high_weight_peers() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions { action_queue_capacity: 1, ..NodeOptions::default() },
+        );
+        node.handle_neighbour_up_with_weight(1, 1);
+        node.handle_neighbour_up_with_weight(2, 10);
+
+        node.eager_push(GossipMessage { sender: 3, message_id: 100, round: 0 });
+
+        assert_eq!(node.dropped_messages(&1), 1);
+        assert_eq!(node.dropped_messages(&2), 0);
+        match node.poll_action().unwrap() {
+            Action::Send { destination, .. } => assert_eq!(destination, 2),
+            other => panic!("expected peer 2's retained GOSSIP, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_from_a_much_higher_weight_sender_stays_eager() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions { prune_weight_margin: 2, ..NodeOptions::default() },
+        );
+        node.handle_neighbour_up_with_weight(1, 1);
+        node.handle_neighbour_up_with_weight(2, 10);
+
+        node.handle_message(Message::Gossip(GossipMessage { sender: 1, message_id: 100, round: 0 }));
+        node.handle_message(Message::Gossip(GossipMessage { sender: 2, message_id: 100, round: 1 }));
+
+        assert!(node.eager_push_peers.contains(&2));
+        assert!(!node.lazy_push_peers.contains(&2));
+    }
+
+    #[test]
+    fn repeated_duplicates_from_a_high_weight_sender_still_cross_the_score_threshold() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions {
+                prune_weight_margin: 2,
+                score_threshold: -1.0,
+                score_decay: 1.0,
+                ..NodeOptions::default()
+            },
+        );
+        node.handle_neighbour_up_with_weight(1, 1);
+        node.handle_neighbour_up_with_weight(2, 10);
+
+        node.handle_message(Message::Gossip(GossipMessage { sender: 1, message_id: 100, round: 0 }));
+        // Stake alone doesn't buy immunity: enough duplicates still cross the score threshold.
+        for round in 1..5 {
+            node.handle_message(Message::Gossip(GossipMessage { sender: 2, message_id: 100, round }));
+        }
+
+        assert!(node.peer_score(&2) < -1.0);
+        assert!(!node.eager_push_peers.contains(&2));
+        assert!(node.lazy_push_peers.contains(&2));
+    }
+
+    #[test]
+    fn duplicate_from_a_comparable_weight_sender_is_pruned_to_lazy() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions { prune_weight_margin: 2, ..NodeOptions::default() },
+        );
+        node.handle_neighbour_up_with_weight(1, 1);
+        node.handle_neighbour_up_with_weight(2, 1);
+
+        node.handle_message(Message::Gossip(GossipMessage { sender: 1, message_id: 100, round: 0 }));
+        node.handle_message(Message::Gossip(GossipMessage { sender: 2, message_id: 100, round: 1 }));
+
+        assert!(!node.eager_push_peers.contains(&2));
+        assert!(node.lazy_push_peers.contains(&2));
+    }
+
+    #[test]
+    fn repeated_churn_demotes_a_peer_below_threshold_and_prunes_it() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions { score_threshold: -1.0, score_decay: 1.0, ..NodeOptions::default() },
+        );
+        node.handle_neighbour_up(1);
+        assert!(node.eager_push_peers.contains(&1));
+
+        // SCORE_CHURN is -0.1 per GRAFT/PRUNE; enough of them cross the -1.0 threshold.
+        for _ in 0..11 {
+            node.handle_message(Message::Graft(GraftMessage { sender: 1, message_id: None, round: 0 }));
+        }
+
+        assert!(node.peer_score(&1) < -1.0);
+        assert!(!node.eager_push_peers.contains(&1));
+        assert!(node.lazy_push_peers.contains(&1));
+
+        let mut demoted = false;
+        while let Some(action) = node.poll_action() {
+            if matches!(action, Action::Send { message: Message::Prune(_), .. }) {
+                demoted = true;
+            }
+        }
+        assert!(demoted);
+    }
+
+    #[test]
+    fn stale_ihave_score_is_only_applied_on_a_genuine_retry_failure() {
+        let mut node: Node<u32, u32> = Node::with_options(
+            0,
+            NodeOptions { ihave_timeout1: 5, ihave_timeout2: 1, ..NodeOptions::default() },
+        );
+        node.handle_neighbour_up(1);
+        node.handle_neighbour_up(2);
+        // Two candidates for the same message, so there's a fallback to retry with.
+        node.handle_message(Message::Ihave(IhaveMessage { sender: 2, message_id: 100, round: 0 }));
+        node.handle_message(Message::Ihave(IhaveMessage { sender: 1, message_id: 100, round: 1 }));
+
+        for _ in 0..5 {
+            node.handle_tick();
+        }
+        // The first (expected) GRAFT timer expiry, onto the lowest-round candidate (sender 2),
+        // just fires a GRAFT; it isn't evidence of a failure yet, so nobody is penalized.
+        assert_eq!(node.peer_score(&2), 0.0);
+        assert_eq!(node.peer_score(&1), 0.0);
+
+        node.handle_tick();
+        // The follow-up retry (onto sender 1) means sender 2, the first GRAFT target, never
+        // delivered.
+        assert!(node.peer_score(&2) < 0.0);
+        assert_eq!(node.peer_score(&1), 0.0);
     }
 }